@@ -5,9 +5,33 @@ use std::{
     alloc::Layout,
     io,
     ops::Range,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, Weak},
 };
 
+/// Round `addr` up to the next multiple of `align`, which must be a power of two
+/// (guaranteed by `Layout`).
+fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+/// Reversed Castagnoli (CRC-32C) polynomial, as used by iSCSI/SCTP.
+const CRC32C_POLY: u32 = 0x82f6_3b78;
+
+fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32C_POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
 struct Node {
     fa: Arc<MemoryRegion>,
     root: Arc<MemoryRegion>,
@@ -31,9 +55,74 @@ pub struct MemoryRegion {
     length: usize,
     key: u32,
     kind: Kind,
-    sub: Mutex<Vec<Range<usize>>>,
+    sub: Mutex<Vec<SubEntry>>,
+}
+
+/// Access mode a sub-region was handed out with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Shared; any number of read-only handles may coexist over the same range.
+    ReadOnly,
+    /// Exclusive; no other handle may overlap this range.
+    Exclusive,
+}
+
+/// A single live sub-region handle: the range it covers, its access mode, and a weak
+/// link to the handle itself. `ReadOnly` entries may carry the same or overlapping
+/// ranges; each is torn down independently, by identity, in `Drop`.
+struct SubEntry {
+    range: Range<usize>,
+    access: Access,
+    child: Weak<MemoryRegion>,
+}
+
+fn ranges_overlap(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
 }
 
+/// Gap-selection policy used by [`MemoryRegion::alloc_with_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocPolicy {
+    /// Take the first gap that satisfies the request.
+    FirstFit,
+    /// Take the smallest gap that satisfies the request, to reduce fragmentation.
+    BestFit,
+}
+
+/// Errors returned by this module's fallible [`MemoryRegion`]/[`RemoteMemoryRegion`]
+/// operations: slicing, allocation, and integrity checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MrError {
+    /// The requested range is empty or falls outside the region.
+    InvalidRange,
+    /// The requested range overlaps an existing sub-region.
+    Overlap,
+    /// No free gap is large enough for the request.
+    NoSpace,
+    /// A gap was large enough, but no offset in it satisfied the alignment.
+    Misaligned,
+    /// The checksum recomputed locally did not match the one carried over the wire.
+    ChecksumMismatch,
+    /// This region isn't locally mapped (it's a remote peer's).
+    NotLocal,
+}
+
+impl std::fmt::Display for MrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            MrError::InvalidRange => "invalid range",
+            MrError::Overlap => "range overlaps an existing sub-region",
+            MrError::NoSpace => "no gap large enough for the request",
+            MrError::Misaligned => "no gap satisfies the requested alignment",
+            MrError::ChecksumMismatch => "checksum mismatch",
+            MrError::NotLocal => "region is not locally mapped",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl std::error::Error for MrError {}
+
 impl MemoryRegion {
     pub fn is_node(&self) -> bool {
         matches!(self.kind, Kind::LocalNode(_) | Kind::RemoteNode(_))
@@ -63,9 +152,29 @@ impl MemoryRegion {
             addr: self.addr() as _,
             len: self.length(),
             rkey: self.rkey(),
+            crc32c: None,
         }
     }
 
+    /// Like [`MemoryRegion::remote_mr`], but also attaches a checksum a peer can later
+    /// check with [`RemoteMemoryRegion::verify_against`].
+    pub fn remote_mr_checked(&self) -> Result<RemoteMemoryRegion, MrError> {
+        Ok(RemoteMemoryRegion {
+            crc32c: Some(self.checksum()?),
+            ..self.remote_mr()
+        })
+    }
+
+    /// CRC32C checksum over this region's bytes. Fails with [`MrError::NotLocal`]
+    /// instead of reading through `self.addr` when it's a peer's virtual address.
+    pub fn checksum(&self) -> Result<u32, MrError> {
+        if !self.is_local() {
+            return Err(MrError::NotLocal);
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(self.addr as *const u8, self.length) };
+        Ok(crc32c(bytes))
+    }
+
     pub(super) fn inner_mr(&self) -> *mut rdma_sys::ibv_mr {
         if let Kind::LocalRoot(lroot) = &self.kind {
             lroot.inner_mr
@@ -74,24 +183,58 @@ impl MemoryRegion {
         }
     }
 
-    pub fn slice(self: &mut Arc<Self>, range: Range<usize>) -> Result<MemoryRegion, ()> {
+    pub fn slice(self: &mut Arc<Self>, range: Range<usize>) -> Result<Arc<MemoryRegion>, MrError> {
         if range.start >= range.end || range.end > self.length {
-            return Err(());
+            return Err(MrError::InvalidRange);
         }
-        if !self
-            .sub
-            .lock()
-            .unwrap()
+        let mut sub = self.sub.lock().unwrap();
+        if sub.iter().any(|entry| ranges_overlap(&entry.range, &range)) {
+            return Err(MrError::Overlap);
+        }
+        let child = self.make_node(range.clone());
+        sub.push(SubEntry {
+            range,
+            access: Access::Exclusive,
+            child: Arc::downgrade(&child),
+        });
+        sub.sort_by_key(|entry| entry.range.start);
+        drop(sub);
+        Ok(child)
+    }
+
+    /// Like [`MemoryRegion::slice`], but a `read_only` request may overlap (exactly or
+    /// partially) any number of existing `ReadOnly` entries instead of failing. A
+    /// writable (`read_only == false`) request still needs exclusive, non-overlapping space.
+    pub fn slice_shared(
+        self: &mut Arc<Self>,
+        range: Range<usize>,
+        read_only: bool,
+    ) -> Result<Arc<MemoryRegion>, MrError> {
+        if !read_only {
+            return self.slice(range);
+        }
+        if range.start >= range.end || range.end > self.length {
+            return Err(MrError::InvalidRange);
+        }
+        let mut sub = self.sub.lock().unwrap();
+        if sub
             .iter()
-            .all(|sub_range| range.end <= sub_range.start || range.start >= sub_range.end)
+            .any(|entry| entry.access == Access::Exclusive && ranges_overlap(&entry.range, &range))
         {
-            return Err(());
+            return Err(MrError::Overlap);
         }
-        self.sub.lock().unwrap().push(range.clone());
-        self.sub
-            .lock()
-            .unwrap()
-            .sort_by(|a, b| a.start.cmp(&b.start));
+        let child = self.make_node(range.clone());
+        sub.push(SubEntry {
+            range,
+            access: Access::ReadOnly,
+            child: Arc::downgrade(&child),
+        });
+        sub.sort_by_key(|entry| entry.range.start);
+        drop(sub);
+        Ok(child)
+    }
+
+    fn make_node(self: &Arc<Self>, range: Range<usize>) -> Arc<MemoryRegion> {
         let new_node = Node {
             fa: self.clone(),
             root: self.root(),
@@ -101,7 +244,7 @@ impl MemoryRegion {
         } else {
             Kind::RemoteNode(new_node)
         };
-        Ok(MemoryRegion {
+        Arc::new(MemoryRegion {
             addr: self.addr + range.start,
             length: range.len(),
             key: self.key,
@@ -110,28 +253,142 @@ impl MemoryRegion {
         })
     }
 
-    pub fn alloc(self: &mut Arc<Self>, layout: Layout) -> Result<MemoryRegion, ()> {
-        let range = {
-            let mut last = 0;
-            let mut ans = Err(());
-            for range in self.sub.lock().unwrap().iter() {
-                if last + layout.size() <= range.start {
-                    ans = Ok(last..last + layout.size());
-                    break;
+    pub fn alloc(self: &mut Arc<Self>, layout: Layout) -> Result<Arc<MemoryRegion>, MrError> {
+        self.alloc_with_policy(layout, AllocPolicy::FirstFit)
+    }
+
+    pub fn alloc_with_policy(
+        self: &mut Arc<Self>,
+        layout: Layout,
+        policy: AllocPolicy,
+    ) -> Result<Arc<MemoryRegion>, MrError> {
+        let range = self.find_gap(layout.size(), layout.align(), policy)?;
+        self.slice(range)
+    }
+
+    /// Find a gap of `size` bytes whose start is aligned to `align`, among the free
+    /// space between (and around) the already-allocated `sub` ranges.
+    fn find_gap(
+        &self,
+        size: usize,
+        align: usize,
+        policy: AllocPolicy,
+    ) -> Result<Range<usize>, MrError> {
+        let sub = self.sub.lock().unwrap();
+        let mut last = 0;
+        let mut large_enough = false;
+        let mut best: Option<(usize, Range<usize>)> = None;
+        // entries may overlap, so track the frontier as a running max of ends
+        let gaps = sub
+            .iter()
+            .map(|entry| (entry.range.start, entry.range.end))
+            .chain(std::iter::once((self.length, self.length)));
+        for (gap_end, entry_end) in gaps {
+            let next_last = last.max(entry_end);
+            let gap_start = last;
+            if gap_end > gap_start {
+                let gap_len = gap_end - gap_start;
+                if gap_len >= size {
+                    large_enough = true;
+                }
+                let aligned_start = align_up(self.addr + gap_start, align) - self.addr;
+                if aligned_start + size <= gap_end {
+                    let candidate = aligned_start..aligned_start + size;
+                    match policy {
+                        AllocPolicy::FirstFit => return Ok(candidate),
+                        AllocPolicy::BestFit => {
+                            if best.as_ref().map_or(true, |(best_len, _)| gap_len < *best_len) {
+                                best = Some((gap_len, candidate));
+                            }
+                        }
+                    }
                 }
-                last = range.end
-            }
-            if last + layout.size() <= self.length {
-                ans = Ok(last..last + layout.size());
             }
-            ans?
-        };
-        self.slice(range)
+            last = next_last;
+        }
+        if let Some((_, candidate)) = best {
+            return Ok(candidate);
+        }
+        if large_enough {
+            Err(MrError::Misaligned)
+        } else {
+            Err(MrError::NoSpace)
+        }
     }
 
     pub fn rkey(&self) -> u32 {
         unsafe { *self.inner_mr() }.rkey
     }
+
+    /// Unallocated byte ranges within this region, to quantify fragmentation.
+    pub fn free_gaps(&self) -> Vec<Range<usize>> {
+        let sub = self.sub.lock().unwrap();
+        let mut gaps = Vec::new();
+        let mut last = 0;
+        // entries may overlap (see find_gap), so track the frontier as a running max
+        for entry in sub.iter() {
+            if entry.range.start > last {
+                gaps.push(last..entry.range.start);
+            }
+            last = last.max(entry.range.end);
+        }
+        if self.length > last {
+            gaps.push(last..self.length);
+        }
+        gaps
+    }
+
+    /// Serializable snapshot of the root→node→sub tree, for offline inspection.
+    pub fn dump_tree(self: &Arc<Self>) -> MemoryRegionSnapshot {
+        let root = self.root();
+        MemoryRegionSnapshot {
+            addr: root.addr,
+            length: root.length,
+            lkey: root.key,
+            rkey: if root.is_local() { root.rkey() } else { 0 },
+            children: root.dump_children(),
+        }
+    }
+
+    fn dump_children(&self) -> Vec<SubRegionSnapshot> {
+        self.sub
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                // a shared range with several live handles shows up as several entries
+                let child = entry.child.upgrade();
+                let children = child.as_ref().map_or_else(Vec::new, |c| c.dump_children());
+                SubRegionSnapshot {
+                    offset: entry.range.start,
+                    length: entry.range.len(),
+                    local: child.as_ref().map_or(true, |c| c.is_local()),
+                    subdivided: !children.is_empty(),
+                    children,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Serializable snapshot of a [`MemoryRegion`] produced by [`MemoryRegion::dump_tree`].
+#[derive(Debug, Serialize)]
+pub struct MemoryRegionSnapshot {
+    pub addr: usize,
+    pub length: usize,
+    pub lkey: u32,
+    pub rkey: u32,
+    pub children: Vec<SubRegionSnapshot>,
+}
+
+/// Snapshot of a single live sub-range within a [`MemoryRegionSnapshot`].
+#[derive(Debug, Serialize)]
+pub struct SubRegionSnapshot {
+    pub offset: usize,
+    pub length: usize,
+    pub local: bool,
+    pub subdivided: bool,
+    pub children: Vec<SubRegionSnapshot>,
 }
 
 impl RdmaMemory for MemoryRegion {
@@ -192,17 +449,15 @@ impl Drop for MemoryRegion {
                 assert_eq!(errno, 0);
             }
             Kind::LocalNode(node) | Kind::RemoteNode(node) => {
-                let index = node
-                    .fa
-                    .sub
-                    .lock()
-                    .unwrap()
+                let range = self.addr - node.fa.addr..self.length + self.addr - node.fa.addr;
+                let self_ptr: *const MemoryRegion = self;
+                let mut sub = node.fa.sub.lock().unwrap();
+                // match by handle identity too, since entries can share a range
+                let index = sub
                     .iter()
-                    .position(|x| {
-                        (self.addr - node.fa.addr..self.length + self.addr - node.fa.addr) == *x
-                    })
+                    .position(|entry| entry.range == range && entry.child.as_ptr() == self_ptr)
                     .unwrap();
-                node.fa.sub.lock().unwrap().remove(index);
+                sub.remove(index);
             }
             _ => todo!(),
         }
@@ -214,6 +469,23 @@ pub struct RemoteMemoryRegion {
     pub addr: usize,
     pub len: usize,
     pub rkey: u32,
+    /// Set when the sender opted into integrity checking (see
+    /// [`MemoryRegion::remote_mr_checked`]).
+    pub crc32c: Option<u32>,
+}
+
+impl RemoteMemoryRegion {
+    /// Recompute `mr`'s checksum and compare it against the one carried here. Does
+    /// nothing if no checksum was attached.
+    pub fn verify_against(&self, mr: &MemoryRegion) -> Result<(), MrError> {
+        let Some(expected) = self.crc32c else {
+            return Ok(());
+        };
+        if expected != mr.checksum()? {
+            return Err(MrError::ChecksumMismatch);
+        }
+        Ok(())
+    }
 }
 
 impl RdmaMemory for RemoteMemoryRegion {
@@ -272,4 +544,236 @@ mod tests {
             .unwrap();
         Ok(())
     }
+
+    #[test]
+    fn mr_alloc_misaligned() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mut mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(24, 8).unwrap(), access)?);
+        let a = mr.slice(0..4).unwrap();
+        let b = mr.slice(12..20).unwrap();
+        // remaining gaps are 4..12 (len 8, but starts 4 bytes off an 8-byte boundary)
+        // and 20..24 (len 4, too small) — big enough overall, but nothing aligns
+        let err = mr
+            .alloc(Layout::from_size_align(8, 8).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(err, MrError::Misaligned);
+        drop(a);
+        drop(b);
+        Ok(())
+    }
+
+    #[test]
+    fn mr_alloc_aligned() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mut mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(256, 64).unwrap(), access)?);
+        // a 1-byte sub-region throws off alignment for anything placed right after it
+        let unaligned = mr.alloc(Layout::from_size_align(1, 1).unwrap()).unwrap();
+        let aligned = mr.alloc(Layout::from_size_align(64, 64).unwrap()).unwrap();
+        assert_eq!(aligned.addr() as usize % 64, 0);
+        drop(unaligned);
+        drop(aligned);
+
+        // best-fit should prefer the smallest gap that still satisfies the request
+        let a = mr.alloc(Layout::from_size_align(32, 32).unwrap()).unwrap();
+        let b = mr.alloc(Layout::from_size_align(32, 32).unwrap()).unwrap();
+        drop(a);
+        let best = mr
+            .alloc_with_policy(Layout::from_size_align(16, 16).unwrap(), AllocPolicy::BestFit)
+            .unwrap();
+        assert_eq!(best.addr(), mr.addr());
+        drop(best);
+        drop(b);
+        Ok(())
+    }
+
+    #[test]
+    fn mr_slice_shared() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mut mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(128, 8).unwrap(), access)?);
+        let r1 = mr.slice_shared(0..64, true).unwrap();
+        let r2 = mr.slice_shared(0..64, true).unwrap();
+        assert_eq!(r1.addr(), r2.addr());
+        // a conflicting exclusive request is still rejected
+        assert!(mr.slice(0..64).is_err());
+        drop(r1);
+        // the range is still held by r2, so it's not free yet
+        assert!(mr.slice(0..64).is_err());
+        drop(r2);
+        // now that every shared handle is gone, the range is free again
+        let r3 = mr.slice(0..64).unwrap();
+        drop(r3);
+        Ok(())
+    }
+
+    #[test]
+    fn mr_slice_shared_partial_overlap() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mut mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(128, 8).unwrap(), access)?);
+        // two read-only handles whose ranges only partially overlap (32..64) are both
+        // granted, not just exact-range duplicates
+        let r1 = mr.slice_shared(0..64, true).unwrap();
+        let r2 = mr.slice_shared(32..96, true).unwrap();
+        assert_eq!(r1.length(), 64);
+        assert_eq!(r2.length(), 64);
+
+        // an exclusive request overlapping either of them is still rejected
+        assert!(mr.slice(40..50).is_err());
+        // the union of both ranges (0..96) is occupied; only the tail is free
+        assert_eq!(mr.free_gaps(), vec![96..128]);
+
+        drop(r1);
+        // 32..96 is still held by r2, so the exclusive request still fails
+        assert!(mr.slice(40..50).is_err());
+        // but the head no longer covered by r2 is free again
+        assert_eq!(mr.free_gaps(), vec![0..32, 96..128]);
+
+        drop(r2);
+        assert_eq!(mr.free_gaps(), vec![0..128]);
+        Ok(())
+    }
+
+    #[test]
+    fn mr_slice_shared_does_not_grow_unboundedly() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mut mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(128, 8).unwrap(), access)?);
+        let keep = mr.slice_shared(0..64, true).unwrap();
+        for _ in 0..64 {
+            drop(mr.slice_shared(0..64, true).unwrap());
+        }
+        // every transient handle tore down its own entry on drop; only `keep`'s remains
+        assert_eq!(mr.sub.lock().unwrap().len(), 1);
+        drop(keep);
+        Ok(())
+    }
+
+    #[test]
+    fn mr_checksum() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(128, 8).unwrap(), access)?);
+        let remote = mr.remote_mr_checked().unwrap();
+        assert!(remote.crc32c.is_some());
+        assert!(remote.verify_against(&mr).is_ok());
+
+        // unchecked metadata carries no checksum and always verifies
+        let unchecked = mr.remote_mr();
+        assert!(unchecked.crc32c.is_none());
+        assert!(unchecked.verify_against(&mr).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn mr_checksum_rejects_non_local_region() -> io::Result<()> {
+        // a RemoteRoot's `addr` is a peer's, not something we can dereference locally
+        let remote = MemoryRegion {
+            addr: 0xdead_beef,
+            length: 64,
+            key: 0,
+            kind: super::Kind::RemoteRoot,
+            sub: std::sync::Mutex::new(Vec::new()),
+        };
+        assert_eq!(remote.checksum(), Err(MrError::NotLocal));
+        assert_eq!(remote.remote_mr_checked().err(), Some(MrError::NotLocal));
+
+        let carrying_checksum = RemoteMemoryRegion {
+            addr: 0xdead_beef,
+            len: 64,
+            rkey: 0,
+            crc32c: Some(0x1234),
+        };
+        assert_eq!(
+            carrying_checksum.verify_against(&remote),
+            Err(MrError::NotLocal)
+        );
+
+        // Drop for RemoteRoot isn't implemented in this module (nothing else ever
+        // constructs one here); this value is a pure in-memory stand-in that owns no
+        // RDMA resources, so skip teardown rather than hitting that `todo!()`.
+        std::mem::forget(remote);
+        Ok(())
+    }
+
+    #[test]
+    fn mr_dump_tree() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mut mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(128, 8).unwrap(), access)?);
+        assert_eq!(mr.free_gaps(), vec![0..128]);
+
+        let sub = mr.slice(0..64).unwrap();
+        assert_eq!(mr.free_gaps(), vec![64..128]);
+
+        let tree = mr.dump_tree();
+        assert_eq!(tree.addr, mr.addr() as usize);
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].offset, 0);
+        assert_eq!(tree.children[0].length, 64);
+        assert!(!tree.children[0].subdivided);
+
+        drop(sub);
+        assert_eq!(mr.free_gaps(), vec![0..128]);
+        assert!(mr.dump_tree().children.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn mr_dump_tree_survives_dropping_first_shared_handle() -> io::Result<()> {
+        let access = ibv_access_flags::IBV_ACCESS_LOCAL_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_WRITE
+            | ibv_access_flags::IBV_ACCESS_REMOTE_READ
+            | ibv_access_flags::IBV_ACCESS_REMOTE_ATOMIC;
+        let ctx = Arc::new(Context::open(None)?);
+        let pd = Arc::new(ctx.create_protection_domain()?);
+        let mut mr =
+            Arc::new(pd.alloc_memory_region(Layout::from_size_align(128, 8).unwrap(), access)?);
+        let r1 = mr.slice_shared(0..64, true).unwrap();
+        let mut r2 = mr.slice_shared(0..64, true).unwrap();
+        // r1 and r2 each get their own SubEntry; r1 is dropped first, and r2 is the
+        // surviving handle that goes on to subdivide itself further.
+        drop(r1);
+        let _grandchild = r2.slice(0..32).unwrap();
+        assert!(mr.dump_tree().children[0].subdivided);
+        Ok(())
+    }
 }